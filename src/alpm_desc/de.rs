@@ -0,0 +1,37 @@
+//! Custom (de)serialization helpers for the alpm `desc` format that don't fall out of
+//! `Deserialize`/`Serialize` derive alone.
+
+/// A `%BUILDDATE%`/`%INSTALLDATE%`-style field: a Unix timestamp in whole seconds, stored as a
+/// plain integer, (de)serialized as a `SystemTime`. Used via `#[serde(with = "de::timestamp")]`.
+pub mod timestamp {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    use alpm_desc::de_error::ErrorKind;
+
+    // `PackageDesc`'s `Deserialize` impl (and so this function, reached via `#[serde(with =
+    // "de::timestamp")]`) is generic over any `D: Deserializer<'de>`, so `D::Error` can only be
+    // built through the `serde::de::Error` trait here - there's no way to require a specific
+    // concrete error type (and so no way to hand back the typed `ErrorKind::ExpectedTimestamp`
+    // itself) without breaking that generic impl.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = u64::deserialize(deserializer).map_err(|_| de::Error::custom(ErrorKind::ExpectedTimestamp))?;
+        Ok(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(serde::ser::Error::custom)?
+            .as_secs();
+        serializer.serialize_u64(secs)
+    }
+}
@@ -1,109 +1,94 @@
 //! Errors for serializing the alpm db format
+use std::error::Error as StdError;
 use std::fmt::{self, Display};
-use std::io;
 use std::result::Result as StdResult;
 
-use failure::{Compat, Context, Fail};
 use serde::{de, ser};
 
 /// The error type for deserialization
 #[derive(Debug)]
 pub struct Error {
-    inner: Context<ErrorKind>,
+    kind: ErrorKind,
 }
 
 /// Errors that can occur during deserialization.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Fail)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum ErrorKind {
     /// This format does not support the given operation
-    #[fail(display = "tried to deserialize an unsupported type/context: {}", _0)]
     Unsupported(&'static str),
     /// The deserializer expected a bool
-    #[fail(display = "expected a bool")]
     ExpectedBool,
     /// The deserializer expected a hex-encoded byte
-    #[fail(display = "expected a hex-encoded byte")]
     ExpectedByte,
     /// The deserializer expected an unsigned integer
-    #[fail(display = "expected an unsigned integer")]
     ExpectedUnsigned,
     /// The deserializer expected a signed integer
-    #[fail(display = "expected a signed integer")]
     ExpectedSigned,
     /// The deserializer expected a float
-    #[fail(display = "expected a float")]
     ExpectedFloat,
     /// The deserializer expected a char
-    #[fail(display = "expected a char")]
     ExpectedChar,
     /// The deserializer expected a key (`%NAME%\n`)
-    #[fail(display = "expected a key (`%NAME%\n`)")]
     ExpectedKey,
     /// The deserializer expected an empty string
-    #[fail(display = "expected an empty string")]
     ExpectedEmpty,
+    /// The deserializer expected a Unix timestamp (seconds since the epoch)
+    ExpectedTimestamp,
     /// A Serialize method returned a custom error.
-    #[fail(display = "the type being deserialized reported an error: {}", _0)]
     Custom(String),
 }
 
 impl Error {
     /// Get the kind of this error
     pub fn kind(&self) -> &ErrorKind {
-        self.inner.get_context()
-    }
-
-    /// Get a version of this error that implements `Fail`.
-    ///
-    /// Unfortunately we cannot implement `Fail` for this type because it conflicts with
-    /// `std::error::Error`, which we must implement for serde.
-    pub fn into_fail(self) -> Context<ErrorKind> {
-        self.inner
+        &self.kind
     }
 }
 
-impl ::std::ops::Deref for Error {
-    type Target = Context<ErrorKind>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.inner
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::Unsupported(ctx) => {
+                write!(f, "tried to deserialize an unsupported type/context: {}", ctx)
+            }
+            ErrorKind::ExpectedBool => write!(f, "expected a bool"),
+            ErrorKind::ExpectedByte => write!(f, "expected a hex-encoded byte"),
+            ErrorKind::ExpectedUnsigned => write!(f, "expected an unsigned integer"),
+            ErrorKind::ExpectedSigned => write!(f, "expected a signed integer"),
+            ErrorKind::ExpectedFloat => write!(f, "expected a float"),
+            ErrorKind::ExpectedChar => write!(f, "expected a char"),
+            ErrorKind::ExpectedKey => write!(f, "expected a key (`%NAME%\n`)"),
+            ErrorKind::ExpectedEmpty => write!(f, "expected an empty string"),
+            ErrorKind::ExpectedTimestamp => write!(f, "expected a Unix timestamp"),
+            ErrorKind::Custom(ref msg) => write!(f, "the type being deserialized reported an error: {}", msg),
+        }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        Display::fmt(&self.inner, f)
+        Display::fmt(&self.kind, f)
     }
 }
 
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Error {
-        Error {
-            inner: Context::new(kind),
-        }
+        Error { kind }
     }
 }
 
-impl From<Context<ErrorKind>> for Error {
-    fn from(inner: Context<ErrorKind>) -> Error {
-        Error { inner }
-    }
-}
+impl StdError for Error {}
 
-impl ::std::error::Error for Error {
-    fn description(&self) -> &'static str {
-        "unimplemented - use `Display` implementation"
-    }
-
-    fn cause(&self) -> Option<&::std::error::Error> {
-        let cause = self.inner.cause()?;
-        // we can't return this, so dump out some info
-        eprintln!("  caused by: {}", cause);
-        None
+impl de::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        ErrorKind::Custom(format!("{}", msg)).into()
     }
 }
 
-impl de::Error for Error {
+impl ser::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
         T: Display,
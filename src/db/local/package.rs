@@ -6,12 +6,15 @@ use std::marker::PhantomData;
 use std::time::SystemTime;
 use std::collections::HashMap;
 
-use failure::ResultExt;
 use libflate::gzip::Decoder;
+use md5;
 use mtree::{self, MTree, Entry};
+use sha2::{Digest, Sha256};
 
 use alpm_desc::de;
-use error::{Error, ErrorKind};
+use db::SignatureLevel;
+use error::{Error, ErrorKind, ResultExt};
+use keyring::{Keyring, Trust};
 
 #[derive(Debug)]
 pub struct Package {
@@ -31,13 +34,16 @@ impl Package {
 
         // check package name/version with path
         if desc.name != name {
-            return Err(format_err!(r#"Name on system ("{}") does not match name in package ("{}")"#, name, desc.name)
-            .context(ErrorKind::InvalidLocalPackage(name.to_owned())).into());
+            return Err(Error::msg(format!(
+                r#"Name on system ("{}") does not match name in package ("{}")"#,
+                name, desc.name
+            )).context(ErrorKind::InvalidLocalPackage(name.to_owned())));
         }
         if desc.version != version {
-            return Err(format_err!(r#"Version on system ("{}") does not match version in package ("{}")"#, version, desc.version)
-            .context(ErrorKind::InvalidLocalPackage(name.to_owned())).into());
-
+            return Err(Error::msg(format!(
+                r#"Version on system ("{}") does not match version in package ("{}")"#,
+                version, desc.version
+            )).context(ErrorKind::InvalidLocalPackage(name.to_owned())));
         }
 
         // get mtree
@@ -87,6 +93,16 @@ impl Package {
         &self.desc.arch
     }
 
+    /// When this package was built.
+    pub fn build_date(&self) -> SystemTime {
+        self.desc.build_date
+    }
+
+    /// When this package was installed.
+    pub fn install_date(&self) -> SystemTime {
+        self.desc.install_date
+    }
+
     /// The person who created this package
     pub fn packager(&self) -> &str {
         &self.desc.packager
@@ -166,9 +182,24 @@ impl Package {
 
     /// Make sure a package matches its metadata.
     ///
-    /// There a few different sources of truth for a package. This method (aspires to) make sure
-    /// they are all consistent.
-    pub fn validate(&self) -> io::Result<Vec<ValidationError>> {
+    /// There a few different sources of truth for a package. This method makes sure they are all
+    /// consistent: file existence, type and size against the mtree, checksums against the mtree
+    /// for whichever of `Md5`/`Sha256` are listed in [`validation()`](#method.validation), and
+    /// the package's PGP signature against `keyring` if `Pgp` is listed. `level` controls how a
+    /// signature that isn't fully trusted is reported: `Optional` accepts a package with no
+    /// archive or signature file to check (a signature that is present is still checked and
+    /// reported like any other), `MarginalOk`/`UnknownOk` accept the corresponding trust level instead of
+    /// reporting [`ValidationError::UntrustedSignature`](enum.ValidationError.html). `level` must
+    /// already be a concrete choice; `SignatureLevel::Inherit` has nothing to inherit from here
+    /// and is rejected with an error rather than silently treated as strict.
+    pub fn validate(&self, level: SignatureLevel, keyring: &Keyring) -> Result<Vec<ValidationError>, Error> {
+        if level == SignatureLevel::Inherit {
+            return Err(Error::msg(
+                "SignatureLevel::Inherit must be resolved to a concrete level by the caller \
+                 before validating a package; there is no parent Alpm instance here to inherit from",
+            ));
+        }
+
         info!("validating package {}", self.name());
         let mut errors = Vec::new();
         for file in self.files() {
@@ -180,7 +211,7 @@ impl Package {
                     errors.push(ValidationError::FileNotFound(format!("{}", path.display())));
                     continue
                 },
-                Err(e) => return Err(e)
+                Err(e) => return Err(e.into())
             };
             // Check file type
             if let Some(ty) = file.file_type() {
@@ -209,9 +240,90 @@ impl Package {
                     });
                 }
             }
+            // Check checksums, but only for regular files that are still there
+            if file.file_type() == Some(mtree::FileType::File) {
+                if self.validation().contains(&Validation::Md5) {
+                    if let Some(expected) = file.md5() {
+                        let actual = md5_digest(path)?;
+                        if &actual[..] != expected {
+                            errors.push(ValidationError::WrongChecksum {
+                                algo: ChecksumAlgo::Md5,
+                                expected: hex_encode(expected),
+                                actual: hex_encode(&actual),
+                            });
+                        }
+                    }
+                }
+                if self.validation().contains(&Validation::Sha256) {
+                    if let Some(expected) = file.sha256() {
+                        let actual = sha256_digest(path)?;
+                        if actual.as_slice() != expected {
+                            errors.push(ValidationError::WrongChecksum {
+                                algo: ChecksumAlgo::Sha256,
+                                expected: hex_encode(expected),
+                                actual: hex_encode(&actual),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.validation().contains(&Validation::Pgp) {
+            self.validate_signature(level, keyring, &mut errors)?;
         }
+
         Ok(errors)
     }
+
+    /// Verify the package's detached PGP signature, pushing a [`ValidationError`] unless the
+    /// trust level `level` allows is met.
+    fn validate_signature(
+        &self,
+        level: SignatureLevel,
+        keyring: &Keyring,
+        errors: &mut Vec<ValidationError>,
+    ) -> Result<(), Error> {
+        let archive = match fs::read(self.path.join("archive")) {
+            Ok(archive) => archive,
+            Err(_) if level == SignatureLevel::Optional => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let signature = match fs::read(self.path.join("signature")) {
+            Ok(signature) => signature,
+            Err(_) if level == SignatureLevel::Optional => return Ok(()),
+            Err(_) => {
+                errors.push(ValidationError::UntrustedSignature);
+                return Ok(());
+            }
+        };
+
+        match keyring.verify(&archive, &signature)? {
+            Trust::Good => (),
+            Trust::Marginal if level == SignatureLevel::MarginalOk => (),
+            Trust::Unknown if level == SignatureLevel::UnknownOk => (),
+            Trust::Marginal | Trust::Unknown => errors.push(ValidationError::UntrustedSignature),
+            Trust::Bad => errors.push(ValidationError::BadSignature),
+        }
+        Ok(())
+    }
+}
+
+/// The raw MD5 digest of the file at `path`, to compare against an `mtree::Entry::md5()`.
+fn md5_digest(path: &Path) -> io::Result<[u8; 16]> {
+    let data = fs::read(path)?;
+    Ok(md5::compute(&data).0)
+}
+
+/// The raw SHA-256 digest of the file at `path`, to compare against an `mtree::Entry::sha256()`.
+fn sha256_digest(path: &Path) -> io::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    Ok(Sha256::digest(&data).to_vec())
+}
+
+/// Hex-encode a digest for display in a [`ValidationError::WrongChecksum`](enum.ValidationError.html).
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -226,10 +338,15 @@ struct PackageDesc {
     url: String,
     license: Option<String>,
     arch: String,
-    //build_date: SystemTime,
-    //install_date: SystemTime,
+    #[serde(rename = "builddate")]
+    #[serde(with = "de::timestamp")]
+    build_date: SystemTime,
+    #[serde(rename = "installdate")]
+    #[serde(with = "de::timestamp")]
+    install_date: SystemTime,
     packager: String,
     reason: Option<Reason>,
+    #[serde(default)]
     validation: Vec<Validation>,
     size: u64,
     #[serde(default)]
@@ -251,7 +368,7 @@ struct Files {
     files: Vec<PathBuf>
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Validation {
     #[serde(rename = "none")]
     None,
@@ -317,26 +434,90 @@ impl From<fs::FileType> for FileType {
     }
 }
 
+/// A checksum algorithm recorded against a file's mtree entry.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ChecksumAlgo {
+    Md5,
+    Sha256,
+}
+
+impl fmt::Display for ChecksumAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChecksumAlgo::Md5 => f.write_str("md5"),
+            ChecksumAlgo::Sha256 => f.write_str("sha256"),
+        }
+    }
+}
+
 /// Possible problems with a package.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Fail)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum ValidationError {
     /// A file in the package is not present on disk.
-    #[fail(display = "file missing at \"{}\"", _0)]
     FileNotFound(String),
     /// A file is the wrong type
-    #[fail(display = "database says file should be a {}, found a {}", expected, actual)]
     WrongType {
         expected: FileType,
         actual: FileType,
     },
     /// A file is the wrong size
-    #[fail(display = "database says file should be {} bytes, found {}", expected, actual)]
     WrongSize {
         expected: u64,
         actual: u64,
     },
+    /// A file's checksum does not match the one recorded in the mtree.
+    WrongChecksum {
+        algo: ChecksumAlgo,
+        expected: String,
+        actual: String,
+    },
+    /// The package's PGP signature does not verify.
+    BadSignature,
+    /// The package's PGP signature verifies, but against a key this keyring does not trust
+    /// enough for the configured `SignatureLevel`; also reported when the package has no
+    /// signature file and the level is not `Optional`.
+    UntrustedSignature,
+    /// An installed ELF binary `DT_NEEDED`s a soname that no installed package provides.
+    MissingLibrary {
+        binary: PathBuf,
+        soname: String,
+    },
 }
 
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::FileNotFound(ref path) => write!(f, "file missing at \"{}\"", path),
+            ValidationError::WrongType { expected, actual } => write!(
+                f,
+                "database says file should be a {}, found a {}",
+                expected, actual
+            ),
+            ValidationError::WrongSize { expected, actual } => write!(
+                f,
+                "database says file should be {} bytes, found {}",
+                expected, actual
+            ),
+            ValidationError::WrongChecksum { algo, ref expected, ref actual } => write!(
+                f,
+                "{} checksum mismatch: database says \"{}\", found \"{}\"",
+                algo, expected, actual
+            ),
+            ValidationError::BadSignature => write!(f, "package signature does not verify"),
+            ValidationError::UntrustedSignature => {
+                write!(f, "package signature does not meet the required trust level")
+            }
+            ValidationError::MissingLibrary { ref binary, ref soname } => write!(
+                f,
+                "\"{}\" needs \"{}\", which no installed package provides",
+                binary.display(), soname
+            ),
+        }
+    }
+}
+
+impl ::std::error::Error for ValidationError {}
+
 impl ValidationError {
     /// Constructor for FileNotFound variant
     fn file_not_found(s: impl Into<String>) -> ValidationError {
@@ -13,7 +13,6 @@ use std::rc::{Rc, Weak as WeakRc};
 
 use atoi::atoi;
 use error::{Error, ErrorKind};
-use failure::{err_msg, Fail, ResultExt};
 use fs2::FileExt;
 use Handle;
 
@@ -22,6 +21,7 @@ mod sync;
 
 pub(crate) use self::local::LocalDatabaseInner;
 pub use self::local::{LocalDatabase, LocalDbPackage};
+pub use self::local::package::ValidationError;
 pub use self::sync::SyncDatabase;
 pub(crate) use self::sync::{SyncDatabaseInner, SyncDbName};
 
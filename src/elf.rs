@@ -0,0 +1,115 @@
+//! Detecting installed packages that are missing a shared-library dependency.
+//!
+//! This is an ELF-aware companion to [`Package::validate()`](db/local/package/struct.Package.html#method.validate):
+//! it parses the dynamic section of every installed ELF file for `DT_NEEDED` sonames and
+//! `DT_RPATH`/`DT_RUNPATH` search paths, then checks each `NEEDED` entry against the sonames
+//! every installed package `provides` (normalized from the alpm `libfoo.so=1` lib-provides form
+//! into the `libfoo.so.1` soname form) or ships as an installed file. This catches the
+//! "installed but broken after a soname bump" case that size/type/checksum validation cannot
+//! see.
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use goblin::elf::Elf;
+use mtree;
+
+use db::{LocalDatabase, ValidationError};
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// The dynamic-linking information extracted from a single installed ELF file.
+struct ElfInfo {
+    needed: Vec<String>,
+    /// `DT_RPATH`/`DT_RUNPATH` entries, with `$ORIGIN` already expanded against the binary's
+    /// containing directory.
+    search_dirs: Vec<PathBuf>,
+}
+
+/// Parse the ELF dynamic section of the file at `path`. Returns `None` for anything that isn't
+/// an ELF file, rather than treating it as an error.
+fn parse(path: &Path) -> io::Result<Option<ElfInfo>> {
+    let data = fs::read(path)?;
+    if !data.starts_with(ELF_MAGIC) {
+        return Ok(None);
+    }
+    let elf = match Elf::parse(&data) {
+        Ok(elf) => elf,
+        Err(_) => return Ok(None),
+    };
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("/"));
+    let search_dirs = elf
+        .rpaths
+        .iter()
+        .chain(elf.runpaths.iter())
+        .flat_map(|paths| paths.split(':'))
+        .map(|entry| PathBuf::from(entry.replace("$ORIGIN", &dir.to_string_lossy())))
+        .collect();
+
+    Ok(Some(ElfInfo {
+        needed: elf.libraries.iter().map(|&s| s.to_owned()).collect(),
+        search_dirs,
+    }))
+}
+
+/// Turn an alpm lib-provides string (`libfoo.so=1`, meaning "provides `libfoo.so` at major
+/// version 1") into the soname form (`libfoo.so.1`) that actually shows up in `DT_NEEDED`.
+/// Returns `None` for a `provides` entry that isn't of this form (a regular package name, say).
+fn normalize_soname_provide(provide: &str) -> Option<String> {
+    let idx = provide.find('=')?;
+    let name = &provide[..idx];
+    let version = &provide[idx + 1..];
+    if !name.ends_with(".so") {
+        return None;
+    }
+    let major = version.split('-').next().unwrap_or(version);
+    Some(format!("{}.{}", name, major))
+}
+
+/// Scan every ELF file installed under `root` and report any `DT_NEEDED` soname that is
+/// satisfied by neither an installed package's `provides`/installed files nor the binary's own
+/// rpath/runpath.
+pub fn check_missing_libraries(db: &LocalDatabase, root: &Path) -> io::Result<Vec<ValidationError>> {
+    let mut provided: HashSet<String> = db
+        .packages()
+        .flat_map(|pkg| pkg.provides().filter_map(normalize_soname_provide).collect::<Vec<_>>())
+        .collect();
+    provided.extend(db.packages().flat_map(|pkg| {
+        pkg.file_names()
+            .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .collect::<Vec<_>>()
+    }));
+
+    let mut errors = Vec::new();
+    for pkg in db.packages() {
+        for file in pkg.files() {
+            if file.file_type() != Some(mtree::FileType::File) {
+                continue;
+            }
+            let path = root.join(file.path());
+            let info = match parse(&path) {
+                Ok(Some(info)) => info,
+                Ok(None) => continue,
+                // a file that's supposed to be here but isn't is size/type validation's job
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+
+            for soname in info.needed {
+                if provided.contains(&soname) {
+                    continue;
+                }
+                if info.search_dirs.iter().any(|dir| dir.join(&soname).is_file()) {
+                    continue;
+                }
+                errors.push(ValidationError::MissingLibrary {
+                    binary: path.clone(),
+                    soname,
+                });
+            }
+        }
+    }
+    Ok(errors)
+}
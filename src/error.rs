@@ -0,0 +1,132 @@
+//! The crate's top-level error type.
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::io;
+use std::result::Result as StdResult;
+
+use mtree;
+
+use alpm_desc::de_error;
+
+/// The error type used throughout this crate.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    source: Option<Box<dyn StdError + Send + Sync + 'static>>,
+}
+
+/// The ways an operation against a local or sync database can fail.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ErrorKind {
+    /// A local package on disk did not match the name/version it was looked up under, or its
+    /// `desc` metadata was malformed (a malformed `mtree` is reported as `Mtree` or `Io`).
+    InvalidLocalPackage(String),
+    /// An I/O error occurred.
+    Io,
+    /// The package's mtree metadata could not be parsed.
+    Mtree,
+    /// A catch-all for an error described only by a message.
+    Custom(String),
+}
+
+impl Error {
+    /// Build an ad-hoc error out of a message, with no further source.
+    pub fn msg(msg: impl Into<String>) -> Error {
+        Error {
+            kind: ErrorKind::Custom(msg.into()),
+            source: None,
+        }
+    }
+
+    /// Get the kind of this error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Wrap this error with a more specific `ErrorKind`, keeping the original as its `source()`.
+    pub fn context(self, kind: ErrorKind) -> Error {
+        Error {
+            kind,
+            source: Some(Box::new(self)),
+        }
+    }
+}
+
+/// Extension trait for attaching an [`ErrorKind`](enum.ErrorKind.html) to any error, turning it
+/// into an [`Error`](struct.Error.html) with the original preserved as its `source()`.
+pub trait ResultExt<T> {
+    /// Wrap the error case of this result with the given `ErrorKind`.
+    fn context(self, kind: ErrorKind) -> StdResult<T, Error>;
+}
+
+impl<T, E> ResultExt<T> for StdResult<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    fn context(self, kind: ErrorKind) -> StdResult<T, Error> {
+        self.map_err(|e| Error {
+            kind,
+            source: Some(Box::new(e)),
+        })
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::InvalidLocalPackage(ref name) => {
+                write!(f, "local package \"{}\" is invalid", name)
+            }
+            ErrorKind::Io => write!(f, "an I/O error occurred"),
+            ErrorKind::Mtree => write!(f, "could not parse package mtree metadata"),
+            ErrorKind::Custom(ref msg) => Display::fmt(msg, f),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.kind, f)
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source.as_ref().map(|e| e.as_ref() as &(dyn StdError + 'static))
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error { kind, source: None }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error {
+            kind: ErrorKind::Io,
+            source: Some(Box::new(e)),
+        }
+    }
+}
+
+impl From<mtree::Error> for Error {
+    fn from(e: mtree::Error) -> Error {
+        Error {
+            kind: ErrorKind::Mtree,
+            source: Some(Box::new(e)),
+        }
+    }
+}
+
+impl From<de_error::Error> for Error {
+    fn from(e: de_error::Error) -> Error {
+        Error {
+            kind: ErrorKind::Custom(format!("{}", e)),
+            source: Some(Box::new(e)),
+        }
+    }
+}
+
+pub type Result<T> = StdResult<T, Error>;
@@ -0,0 +1,62 @@
+//! Verifying package signatures against a trusted PGP keyring.
+use std::path::PathBuf;
+
+use gpgme::{self, Protocol, SignatureSummary};
+
+use error::Error;
+
+/// A configurable set of trusted PGP keys, used to verify package signatures.
+#[derive(Debug, Clone)]
+pub struct Keyring {
+    home_dir: PathBuf,
+}
+
+/// How trustworthy a verified signature's key turned out to be.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Trust {
+    /// Signed by a fully-trusted key.
+    Good,
+    /// Signed by a key that is only marginally trusted.
+    Marginal,
+    /// Signed by a key this keyring has no trust information for.
+    Unknown,
+    /// The signature does not verify against the given data (wrong key, corrupted data, etc).
+    Bad,
+}
+
+impl Keyring {
+    /// Use the gpg keyring rooted at `home_dir` (as produced by `gpg --homedir`).
+    pub fn new(home_dir: impl Into<PathBuf>) -> Keyring {
+        Keyring {
+            home_dir: home_dir.into(),
+        }
+    }
+
+    /// Verify a detached `signature` over `data`, returning how trustworthy the signing key is.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<Trust, Error> {
+        let mut ctx = gpgme::Context::from_protocol(Protocol::OpenPgp)
+            .map_err(|e| Error::msg(format!("failed to start gpgme: {}", e)))?;
+        ctx.set_engine_home_dir(self.home_dir.to_string_lossy().into_owned())
+            .map_err(|e| Error::msg(format!("failed to load keyring: {}", e)))?;
+
+        let result = ctx
+            .verify_detached(signature, data)
+            .map_err(|e| Error::msg(format!("signature did not verify: {}", e)))?;
+
+        let sig = result
+            .signatures()
+            .next()
+            .ok_or_else(|| Error::msg("signature contained no data"))?;
+        let summary = sig.summary();
+
+        Ok(if summary.contains(SignatureSummary::VALID) {
+            Trust::Good
+        } else if summary.contains(SignatureSummary::RED) {
+            Trust::Bad
+        } else if summary.contains(SignatureSummary::GREEN) {
+            Trust::Marginal
+        } else {
+            Trust::Unknown
+        })
+    }
+}
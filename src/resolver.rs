@@ -0,0 +1,265 @@
+//! Dependency resolution over the local and sync databases.
+//!
+//! [`Package::depends()`](../db/local/package/struct.Package.html::depends) and friends only
+//! hand back raw dependency strings; this module is what turns those strings into an ordered
+//! list of packages to install, by walking `provides`/`conflicts`/`replaces` across the
+//! databases that are searched.
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+use db::{LocalDatabase, LocalDbPackage, SyncDatabase};
+use version::{Constraint, Version};
+
+/// Resolves a set of target package names into an ordered install list.
+///
+/// Packages that are looked up while resolving are cached by name+version, so a dependency that
+/// is pulled in by several targets (possibly under different aliases, via `provides`/`replaces`)
+/// is held as a single shared `Rc`. Each name is searched for in the databases only the first
+/// time it is seen; an alias not seen before is searched for again before its result is matched
+/// to the cached entry.
+///
+/// Note: a package found in a sync database is represented the same way as one found in the
+/// local database (both as `Rc<LocalDbPackage>`), since the sync db's own package type isn't
+/// available to this module. Callers that need to distinguish "already installed" from
+/// "available to install" should check `local.package(pkg.name())` themselves.
+pub struct DependencyResolver<'a> {
+    local: &'a LocalDatabase,
+    sync: &'a [SyncDatabase],
+    cache: RefCell<HashMap<(String, String), Rc<LocalDbPackage>>>,
+    by_name: RefCell<HashMap<String, Rc<LocalDbPackage>>>,
+}
+
+impl<'a> DependencyResolver<'a> {
+    /// Create a resolver that searches the given local database and, in order, the given sync
+    /// databases.
+    pub fn new(local: &'a LocalDatabase, sync: &'a [SyncDatabase]) -> DependencyResolver<'a> {
+        DependencyResolver {
+            local,
+            sync,
+            cache: RefCell::new(HashMap::new()),
+            by_name: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve `targets` into an install list, ordered so that every package appears after
+    /// everything it depends on.
+    pub fn resolve(&self, targets: &[&str]) -> Result<Vec<Rc<LocalDbPackage>>, ResolveError> {
+        let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+        let mut queue: Vec<String> = targets.iter().map(|t| (*t).to_owned()).collect();
+        let mut seen = HashSet::new();
+
+        while let Some(name) = queue.pop() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let pkg = self.find(&name).ok_or_else(|| ResolveError::Unsatisfiable(name.clone()))?;
+
+            let mut deps = Vec::new();
+            for dep in pkg.depends() {
+                let constraint = Constraint::parse(dep);
+                let dep_pkg = self
+                    .find(&constraint.name)
+                    .ok_or_else(|| ResolveError::Unsatisfiable(constraint.name.clone()))?;
+                if let Some(version) = provided_version(&dep_pkg, &constraint.name) {
+                    if !constraint.matches(&version) {
+                        return Err(ResolveError::VersionMismatch {
+                            name: constraint.name.clone(),
+                            constraint: constraint.to_string(),
+                            found: version.to_string(),
+                        });
+                    }
+                }
+                deps.push(constraint.name.clone());
+                queue.push(constraint.name);
+            }
+            graph.insert(name, deps);
+        }
+
+        // Conflicts and replacements only really mean something once the full transitive set of
+        // packages being installed is known, so check them in a second pass over `seen` instead
+        // of while the queue above is still being drained (a package queued but not yet visited
+        // would otherwise be invisible to an earlier package's conflict/replaces check).
+        for name in &seen {
+            let pkg = self.find(name).ok_or_else(|| ResolveError::Unsatisfiable(name.clone()))?;
+            // a name that resolves back to `pkg` itself (something it provides, or the old name
+            // it replaces) is not a second package
+            let is_other = |other: &str| {
+                self.find(other).map_or(true, |found| !Rc::ptr_eq(&found, &pkg))
+            };
+
+            for conflict in pkg.conflicts() {
+                let conflict_name = Constraint::parse(conflict).name;
+                if conflict_name != pkg.name() && seen.contains(&conflict_name) && is_other(&conflict_name) {
+                    return Err(ResolveError::Conflict {
+                        a: pkg.name().to_owned(),
+                        b: conflict_name,
+                    });
+                }
+            }
+            for replaces in pkg.replaces() {
+                let replaced_name = Constraint::parse(replaces).name;
+                if replaced_name != pkg.name() && seen.contains(&replaced_name) && is_other(&replaced_name) {
+                    return Err(ResolveError::Conflict {
+                        a: pkg.name().to_owned(),
+                        b: replaced_name,
+                    });
+                }
+            }
+        }
+
+        self.topo_sort(&graph)
+    }
+
+    /// Find a package by name, by anything that `provides` that name, or by anything that
+    /// `replaces` that name, checking the cache before falling back to the local database and
+    /// then each sync database in order.
+    fn find(&self, name: &str) -> Option<Rc<LocalDbPackage>> {
+        if let Some(pkg) = self.by_name.borrow().get(name) {
+            return Some(Rc::clone(pkg));
+        }
+
+        let pkg = self
+            .local
+            .package(name)
+            .or_else(|| self.sync.iter().filter_map(|db| db.package(name)).next())
+            .or_else(|| self.find_provider(name))
+            .or_else(|| self.find_replacer(name))?;
+
+        let key = (pkg.name().to_owned(), pkg.version().to_owned());
+        let pkg = match self.cache.borrow().get(&key) {
+            Some(cached) => Rc::clone(cached),
+            None => Rc::new(pkg),
+        };
+        self.cache.borrow_mut().insert(key, Rc::clone(&pkg));
+        self.by_name.borrow_mut().insert(name.to_owned(), Rc::clone(&pkg));
+        self.by_name.borrow_mut().insert(pkg.name().to_owned(), Rc::clone(&pkg));
+        Some(pkg)
+    }
+
+    /// Search every package in every database for one that `provides` the given (virtual) name.
+    fn find_provider(&self, name: &str) -> Option<LocalDbPackage> {
+        self.local
+            .packages()
+            .find(|pkg| pkg.provides().any(|p| Constraint::parse(p).name == name))
+            .or_else(|| {
+                self.sync.iter().find_map(|db| {
+                    db.packages().find(|pkg| pkg.provides().any(|p| Constraint::parse(p).name == name))
+                })
+            })
+    }
+
+    /// Search every package in every database for one that `replaces` the given name, for when a
+    /// package has been renamed and the old name is no longer provided directly.
+    fn find_replacer(&self, name: &str) -> Option<LocalDbPackage> {
+        self.local
+            .packages()
+            .find(|pkg| pkg.replaces().any(|p| Constraint::parse(p).name == name))
+            .or_else(|| {
+                self.sync.iter().find_map(|db| {
+                    db.packages().find(|pkg| pkg.replaces().any(|p| Constraint::parse(p).name == name))
+                })
+            })
+    }
+
+    /// Topologically order `graph` so that dependencies come before dependents.
+    fn topo_sort(
+        &self,
+        graph: &HashMap<String, Vec<String>>,
+    ) -> Result<Vec<Rc<LocalDbPackage>>, ResolveError> {
+        #[derive(Clone, Copy, Eq, PartialEq)]
+        enum Mark {
+            Visiting,
+            Done,
+        }
+
+        let mut marks: HashMap<&str, Mark> = HashMap::new();
+        let mut order = Vec::new();
+
+        fn visit<'g>(
+            name: &'g str,
+            graph: &'g HashMap<String, Vec<String>>,
+            marks: &mut HashMap<&'g str, Mark>,
+            order: &mut Vec<String>,
+        ) -> Result<(), ResolveError> {
+            match marks.get(name) {
+                Some(Mark::Done) => return Ok(()),
+                Some(Mark::Visiting) => return Err(ResolveError::Cycle(name.to_owned())),
+                None => {}
+            }
+            marks.insert(name, Mark::Visiting);
+            if let Some(deps) = graph.get(name) {
+                for dep in deps {
+                    visit(dep, graph, marks, order)?;
+                }
+            }
+            marks.insert(name, Mark::Done);
+            order.push(name.to_owned());
+            Ok(())
+        }
+
+        for name in graph.keys() {
+            visit(name, graph, &mut marks, &mut order)?;
+        }
+
+        order
+            .into_iter()
+            .map(|name| self.find(&name).ok_or_else(|| ResolveError::Unsatisfiable(name)))
+            .collect()
+    }
+}
+
+/// The version that a dependency constraint naming `name` should be checked against, where
+/// `name` may be `pkg`'s own name or a virtual name it `provides`. Returns `None` when `name` is
+/// only provided without a version (or reached via `replaces`), in which case there is no
+/// version to check and any constraint on it is treated as satisfied.
+fn provided_version(pkg: &LocalDbPackage, name: &str) -> Option<Version> {
+    if pkg.name() == name {
+        return Some(Version::parse(pkg.version()));
+    }
+    pkg.provides()
+        .map(Constraint::parse)
+        .find(|c| c.name == name)
+        .and_then(|c| c.version)
+}
+
+/// Why dependency resolution failed.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ResolveError {
+    /// No installed or available package provides the named dependency.
+    Unsatisfiable(String),
+    /// Two packages that would both be installed conflict with one another.
+    Conflict { a: String, b: String },
+    /// The dependency graph contains a cycle reachable from the package named here.
+    Cycle(String),
+    /// A package was found, but its version did not satisfy the dependency constraint.
+    VersionMismatch {
+        name: String,
+        constraint: String,
+        found: String,
+    },
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResolveError::Unsatisfiable(ref name) => {
+                write!(f, "unable to satisfy dependency \"{}\"", name)
+            }
+            ResolveError::Conflict { ref a, ref b } => {
+                write!(f, "\"{}\" conflicts with \"{}\"", a, b)
+            }
+            ResolveError::Cycle(ref name) => {
+                write!(f, "dependency cycle detected involving \"{}\"", name)
+            }
+            ResolveError::VersionMismatch { ref name, ref constraint, ref found } => write!(
+                f,
+                "dependency \"{}\" requires \"{}\", but \"{}\" is available",
+                name, constraint, found
+            ),
+        }
+    }
+}
+
+impl ::std::error::Error for ResolveError {}
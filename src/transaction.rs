@@ -0,0 +1,301 @@
+//! Applying changes to an install root.
+//!
+//! `db` only knows how to read the local and sync databases; this module is what actually
+//! unpacks a package archive onto disk, updates the local database to match, and removes
+//! packages again, consulting their recorded file list.
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libflate::gzip::Decoder;
+use tar::Archive;
+
+use db::LocalDbPackage;
+use error::Error;
+
+/// Progress reported while a [`Transaction`](struct.Transaction.html) is applied, so a front-end
+/// can drive a progress bar without blocking on extraction, which runs on its own thread.
+#[derive(Debug)]
+pub enum TransactionEvent {
+    /// The total uncompressed size of the archive about to be extracted, in bytes.
+    ArchiveLen(u64),
+    /// A file was written to (by an install) or removed from (by a remove) this path under the
+    /// install root.
+    Extracted(PathBuf),
+    /// The local database entry for this package was updated to reflect the change.
+    DbUpdated(String),
+    /// A file already on disk conflicted with one being installed or removed.
+    Conflict(PathBuf),
+}
+
+/// An install or remove operation against an install root.
+///
+/// Each operation runs on a background thread and reports its progress over the returned
+/// channel, so the caller's thread is never blocked on archive extraction.
+pub struct Transaction {
+    root: PathBuf,
+}
+
+impl Transaction {
+    /// Create a transaction rooted at `root`, or at `/` if `root` is `None`.
+    pub fn new(root: impl Into<Option<PathBuf>>) -> Transaction {
+        Transaction {
+            root: root.into().unwrap_or_else(|| PathBuf::from("/")),
+        }
+    }
+
+    /// Unpack `archive` under this transaction's install root, refusing to overwrite any file
+    /// that already exists, and write the corresponding `desc`/`files`/`mtree` entry into
+    /// `db_dir` (the package's directory inside the local database, e.g.
+    /// `<db path>/local/foo-1.0-1/`).
+    ///
+    /// If extraction fails partway through (including on a conflict), every file written so far
+    /// is removed before the channel is closed, so a failed install never leaves a partial
+    /// package, or a local database entry for one, behind.
+    pub fn install(&self, archive: PathBuf, db_dir: PathBuf) -> Receiver<TransactionEvent> {
+        let (tx, rx) = mpsc::channel();
+        let root = self.root.clone();
+        thread::spawn(move || {
+            let mut written = Vec::new();
+            if let Err(e) = install_inner(&root, &archive, &db_dir, &tx, &mut written) {
+                rollback(&written);
+                warn!("install into \"{}\" failed: {}", db_dir.display(), e);
+            }
+        });
+        rx
+    }
+
+    /// Delete every file recorded against `package` and remove its directory, `db_dir`, from the
+    /// local database.
+    ///
+    /// Files that are already missing are silently skipped (removing a package should not fail
+    /// just because something else already cleaned up after it); files that exist but cannot be
+    /// removed are reported as a [`TransactionEvent::Conflict`](enum.TransactionEvent.html), and
+    /// the local database entry is left in place so the package isn't forgotten while files
+    /// belonging to it remain on disk.
+    pub fn remove(&self, package: &LocalDbPackage, db_dir: PathBuf) -> Receiver<TransactionEvent> {
+        let (tx, rx) = mpsc::channel();
+        let root = self.root.clone();
+        let name = package.name().to_owned();
+        let files: Vec<PathBuf> = package.file_names().map(|p| p.to_owned()).collect();
+        thread::spawn(move || {
+            let mut ok = true;
+            for file in files {
+                let dest = root.join(&file);
+                match fs::remove_file(&dest) {
+                    Ok(()) => {
+                        tx.send(TransactionEvent::Extracted(dest)).ok();
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(_) => {
+                        ok = false;
+                        tx.send(TransactionEvent::Conflict(dest)).ok();
+                    }
+                }
+            }
+            if ok {
+                if let Err(e) = fs::remove_dir_all(&db_dir) {
+                    warn!("failed to remove local database entry \"{}\": {}", db_dir.display(), e);
+                    return;
+                }
+                tx.send(TransactionEvent::DbUpdated(name)).ok();
+            }
+        });
+        rx
+    }
+}
+
+/// Extract `archive` and write its local database entry, recording every file written in
+/// `written` so that the caller can roll them back on any error.
+fn install_inner(
+    root: &Path,
+    archive: &Path,
+    db_dir: &Path,
+    tx: &Sender<TransactionEvent>,
+    written: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    tx.send(TransactionEvent::ArchiveLen(uncompressed_len(archive)?)).ok();
+
+    let mut tar = Archive::new(Decoder::new(fs::File::open(archive)?)?);
+    let mut pkginfo = None;
+    let mut mtree = None;
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+
+        // package control files live in the local database entry, not under the install root
+        if path.starts_with(".PKGINFO") {
+            let mut buf = Vec::new();
+            entry.read_to_end(&mut buf)?;
+            pkginfo = Some(buf);
+            continue;
+        }
+        if path.starts_with(".MTREE") {
+            let mut buf = Vec::new();
+            entry.read_to_end(&mut buf)?;
+            mtree = Some(buf);
+            continue;
+        }
+        if path.starts_with(".BUILDINFO") || path.starts_with(".INSTALL") {
+            continue;
+        }
+
+        if !is_contained(&path) {
+            rollback(&written);
+            return Err(Error::msg(format!(
+                "archive entry \"{}\" escapes the install root, refusing to extract",
+                path.display()
+            )));
+        }
+
+        let dest = root.join(&path);
+        if !entry.header().entry_type().is_dir() && dest.exists() {
+            tx.send(TransactionEvent::Conflict(dest.clone())).ok();
+            rollback(&written);
+            return Err(Error::msg(format!(
+                "refusing to overwrite \"{}\", already present on disk",
+                dest.display()
+            )));
+        }
+        if let Err(e) = entry.unpack(&dest) {
+            rollback(&written);
+            return Err(e.into());
+        }
+        written.push(dest.clone());
+        tx.send(TransactionEvent::Extracted(dest)).ok();
+    }
+
+    if let Err(e) = write_db_entry(db_dir, pkginfo.as_deref(), mtree.as_deref(), root, &written) {
+        rollback(&written);
+        let _ = fs::remove_dir_all(db_dir);
+        return Err(e);
+    }
+
+    let name = db_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| db_dir.display().to_string());
+    tx.send(TransactionEvent::DbUpdated(name)).ok();
+    Ok(())
+}
+
+/// Whether every component of `path` stays under whatever it's joined to, i.e. it has no `..`,
+/// no absolute root, and no (on Windows) drive prefix - tar archives are untrusted input, and
+/// `entry.unpack()` does not guard against a crafted entry writing outside the install root.
+fn is_contained(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// The total size the archive will occupy once decompressed, found by summing its tar entry
+/// sizes. This means decompressing the archive twice (once here, once to actually extract it),
+/// but it keeps `ArchiveLen` honest about what it claims to measure.
+fn uncompressed_len(archive: &Path) -> Result<u64, Error> {
+    let mut tar = Archive::new(Decoder::new(fs::File::open(archive)?)?);
+    let mut total = 0u64;
+    for entry in tar.entries()? {
+        total += entry?.header().size()?;
+    }
+    Ok(total)
+}
+
+/// Write the `desc`/`files`/`mtree` entry for a freshly-extracted package into `db_dir`.
+fn write_db_entry(
+    db_dir: &Path,
+    pkginfo: Option<&[u8]>,
+    mtree: Option<&[u8]>,
+    root: &Path,
+    written: &[PathBuf],
+) -> Result<(), Error> {
+    fs::create_dir_all(db_dir)?;
+
+    if let Some(pkginfo) = pkginfo {
+        fs::write(db_dir.join("desc"), pkginfo_to_desc(pkginfo, SystemTime::now()))?;
+    }
+    if let Some(mtree) = mtree {
+        fs::write(db_dir.join("mtree"), mtree)?;
+    }
+
+    let mut files = String::from("%FILES%\n");
+    for path in written {
+        if let Ok(relative) = path.strip_prefix(root) {
+            files.push_str(&relative.display().to_string());
+            files.push('\n');
+        }
+    }
+    fs::write(db_dir.join("files"), files)?;
+
+    Ok(())
+}
+
+/// Convert a `.PKGINFO` (`key = value` per line) into the alpm `desc` format (`%TAG%` header
+/// followed by one value per line), carrying over the fields `desc` already knows about and
+/// stamping `%INSTALLDATE%` with `install_date`.
+fn pkginfo_to_desc(pkginfo: &[u8], install_date: SystemTime) -> String {
+    let text = String::from_utf8_lossy(pkginfo);
+    let mut fields: HashMap<&str, Vec<&str>> = HashMap::new();
+    for line in text.lines() {
+        if let Some(idx) = line.find('=') {
+            let key = line[..idx].trim();
+            let value = line[idx + 1..].trim();
+            if !key.is_empty() {
+                fields.entry(key).or_insert_with(Vec::new).push(value);
+            }
+        }
+    }
+
+    let install_date = install_date
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .to_string();
+
+    let mut desc = String::new();
+    let mut push = |tag: &str, values: &[&str]| {
+        if values.is_empty() {
+            return;
+        }
+        desc.push_str(&format!("%{}%\n", tag));
+        for value in values {
+            desc.push_str(value);
+            desc.push('\n');
+        }
+        desc.push('\n');
+    };
+
+    push("NAME", fields.get("pkgname").map_or(&[][..], Vec::as_slice));
+    push("VERSION", fields.get("pkgver").map_or(&[][..], Vec::as_slice));
+    push("BASE", fields.get("pkgbase").map_or(&[][..], Vec::as_slice));
+    push("DESC", fields.get("pkgdesc").map_or(&[][..], Vec::as_slice));
+    push("URL", fields.get("url").map_or(&[][..], Vec::as_slice));
+    push("ARCH", fields.get("arch").map_or(&[][..], Vec::as_slice));
+    push("BUILDDATE", fields.get("builddate").map_or(&[][..], Vec::as_slice));
+    push("INSTALLDATE", &[install_date.as_str()]);
+    push("PACKAGER", fields.get("packager").map_or(&[][..], Vec::as_slice));
+    push("SIZE", fields.get("size").map_or(&[][..], Vec::as_slice));
+    push("LICENSE", fields.get("license").map_or(&[][..], Vec::as_slice));
+    push("GROUPS", fields.get("group").map_or(&[][..], Vec::as_slice));
+    push("DEPENDS", fields.get("depend").map_or(&[][..], Vec::as_slice));
+    push("OPTDEPENDS", fields.get("optdepend").map_or(&[][..], Vec::as_slice));
+    push("CONFLICTS", fields.get("conflict").map_or(&[][..], Vec::as_slice));
+    push("PROVIDES", fields.get("provides").map_or(&[][..], Vec::as_slice));
+    push("REPLACES", fields.get("replaces").map_or(&[][..], Vec::as_slice));
+    // write_db_entry copies the archive's own `.MTREE` verbatim rather than computing digests;
+    // both checksum types are listed, and `Package::validate()` skips the check of a digest that
+    // a file's mtree entry does not carry.
+    push("VALIDATION", &["md5", "sha256"]);
+
+    desc
+}
+
+/// Remove every file written so far, in reverse order, after a failed extraction.
+fn rollback(written: &[PathBuf]) {
+    for path in written.iter().rev() {
+        let _ = fs::remove_file(path);
+    }
+}
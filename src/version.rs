@@ -0,0 +1,296 @@
+//! Parsing and comparing the version strings used in alpm dependency entries.
+//!
+//! A full version is `[epoch:]pkgver[-pkgrel]`. Dependency entries additionally carry an
+//! optional comparison operator, e.g. `glibc>=2.28`, `foo=1.0-2`, `bar<3:1.0`.
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+
+/// A parsed `[epoch:]pkgver[-pkgrel]` version string.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Version {
+    epoch: u64,
+    pkgver: String,
+    pkgrel: Option<String>,
+}
+
+impl Version {
+    /// Parse a version string of the form `[epoch:]pkgver[-pkgrel]`.
+    pub fn parse(s: &str) -> Version {
+        let (epoch, rest) = match s.find(':') {
+            Some(idx) => (s[..idx].parse().unwrap_or(0), &s[idx + 1..]),
+            None => (0, s),
+        };
+        let (pkgver, pkgrel) = match rest.rfind('-') {
+            Some(idx) => (rest[..idx].to_owned(), Some(rest[idx + 1..].to_owned())),
+            None => (rest.to_owned(), None),
+        };
+        Version { epoch, pkgver, pkgrel }
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}:", self.epoch)?;
+        }
+        write!(f, "{}", self.pkgver)?;
+        if let Some(ref pkgrel) = self.pkgrel {
+            write!(f, "-{}", pkgrel)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Version) -> Ordering {
+        // alpm only compares `pkgrel` when both sides have one; a bare `pkgver` (no `-pkgrel`)
+        // is treated as matching any `pkgrel` of the same `pkgver` (`vercmp 1.0 1.0-1` == 0).
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| rpmvercmp(&self.pkgver, &other.pkgver))
+            .then_with(|| match (&self.pkgrel, &other.pkgrel) {
+                (Some(a), Some(b)) => rpmvercmp(a, b),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The comparison operator in a dependency constraint (e.g. the `>=` in `glibc>=2.28`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Op {
+    Less,
+    LessEq,
+    Eq,
+    GreaterEq,
+    Greater,
+}
+
+impl Op {
+    fn matches(self, ord: Ordering) -> bool {
+        match self {
+            Op::Less => ord == Ordering::Less,
+            Op::LessEq => ord != Ordering::Greater,
+            Op::Eq => ord == Ordering::Equal,
+            Op::GreaterEq => ord != Ordering::Less,
+            Op::Greater => ord == Ordering::Greater,
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Op::Less => "<",
+            Op::LessEq => "<=",
+            Op::Eq => "=",
+            Op::GreaterEq => ">=",
+            Op::Greater => ">",
+        })
+    }
+}
+
+/// A dependency constraint, e.g. `glibc>=2.28` parses into `{ name: "glibc", op: Some(GreaterEq),
+/// version: Some(2.28) }`. A bare name such as `glibc` has no operator or version.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Constraint {
+    pub name: String,
+    pub op: Option<Op>,
+    pub version: Option<Version>,
+}
+
+impl Constraint {
+    /// Parse a dependency entry such as `glibc>=2.28` or a bare name such as `glibc`.
+    pub fn parse(s: &str) -> Constraint {
+        let idx = s.find(|c| c == '<' || c == '>' || c == '=');
+        let idx = match idx {
+            Some(idx) => idx,
+            None => {
+                return Constraint {
+                    name: s.to_owned(),
+                    op: None,
+                    version: None,
+                }
+            }
+        };
+
+        let name = s[..idx].to_owned();
+        let rest = &s[idx..];
+        let (op, version_start) = if rest.starts_with(">=") {
+            (Op::GreaterEq, 2)
+        } else if rest.starts_with("<=") {
+            (Op::LessEq, 2)
+        } else if rest.starts_with('=') {
+            (Op::Eq, 1)
+        } else if rest.starts_with('<') {
+            (Op::Less, 1)
+        } else {
+            (Op::Greater, 1)
+        };
+
+        Constraint {
+            name,
+            op: Some(op),
+            version: Some(Version::parse(&rest[version_start..])),
+        }
+    }
+
+    /// Whether `version` satisfies this constraint. A constraint with no operator (a bare name)
+    /// is satisfied by any version.
+    pub fn matches(&self, version: &Version) -> bool {
+        match (self.op, &self.version) {
+            (Some(op), Some(ref constraint_version)) => {
+                op.matches(version.cmp(constraint_version))
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let (Some(op), Some(ref version)) = (self.op, &self.version) {
+            write!(f, "{}{}", op, version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two `pkgver`/`pkgrel` segments the way alpm's `rpmvercmp` does: walk both strings,
+/// splitting into maximal alphanumeric runs and skipping separators (`.`, `_`, `+`, `-`),
+/// comparing run by run. Numeric runs are compared numerically (after stripping leading zeros,
+/// longer wins, then lexically) and always outrank alpha runs, which are compared lexically.
+/// Running out of runs while the other string still has a numeric run makes it newer; otherwise
+/// the longer string is older (e.g. an alpha suffix such as `1.0a` is older than plain `1.0`).
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    let mut a = a;
+    let mut b = b;
+
+    loop {
+        a = a.trim_start_matches(is_separator);
+        b = b.trim_start_matches(is_separator);
+
+        if a.is_empty() && b.is_empty() {
+            return Ordering::Equal;
+        }
+        if a.is_empty() {
+            return if starts_numeric(b) { Ordering::Less } else { Ordering::Greater };
+        }
+        if b.is_empty() {
+            return if starts_numeric(a) { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let (a_run, a_rest) = take_run(a);
+        let (b_run, b_rest) = take_run(b);
+        a = a_rest;
+        b = b_rest;
+
+        let a_numeric = starts_numeric(a_run);
+        let b_numeric = starts_numeric(b_run);
+
+        let ord = if a_numeric && b_numeric {
+            let a_trimmed = a_run.trim_start_matches('0');
+            let b_trimmed = b_run.trim_start_matches('0');
+            a_trimmed
+                .len()
+                .cmp(&b_trimmed.len())
+                .then_with(|| a_trimmed.cmp(b_trimmed))
+        } else if a_numeric {
+            Ordering::Greater
+        } else if b_numeric {
+            Ordering::Less
+        } else {
+            a_run.cmp(b_run)
+        };
+
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == '.' || c == '_' || c == '+' || c == '-'
+}
+
+fn starts_numeric(s: &str) -> bool {
+    s.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false)
+}
+
+/// Split off the maximal leading run of either digits or non-digit/non-separator characters.
+fn take_run(s: &str) -> (&str, &str) {
+    let numeric = starts_numeric(s);
+    let end = s
+        .char_indices()
+        .find(|&(_, c)| is_separator(c) || c.is_ascii_digit() != numeric)
+        .map(|(idx, _)| idx)
+        .unwrap_or_else(|| s.len());
+    s.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn rpmvercmp_examples() {
+        let cases = [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0", "2.0", Ordering::Less),
+            ("2.0", "1.0", Ordering::Greater),
+            ("1.0", "1.1", Ordering::Less),
+            ("1.1", "1.0", Ordering::Greater),
+            ("1.0a", "1.0", Ordering::Less),
+            ("1.0", "1.0a", Ordering::Greater),
+            ("1.0a", "1.0b", Ordering::Less),
+            ("1.0.1", "1.0", Ordering::Greater),
+            ("1.009", "1.9", Ordering::Equal),
+            ("1.001", "1.1.1", Ordering::Less),
+            ("2.0beta", "2.0", Ordering::Less),
+            ("a", "b", Ordering::Less),
+        ];
+        for (a, b, expected) in &cases {
+            assert_eq!(rpmvercmp(a, b), *expected, "rpmvercmp({:?}, {:?})", a, b);
+        }
+    }
+
+    #[test]
+    fn version_ordering_considers_epoch_and_pkgrel() {
+        assert_eq!(Version::parse("1:1.0-1").cmp(&Version::parse("2.0-1")), Ordering::Greater);
+        assert_eq!(Version::parse("1.0-2").cmp(&Version::parse("1.0-1")), Ordering::Greater);
+        assert_eq!(Version::parse("1.0").cmp(&Version::parse("1.0-1")), Ordering::Equal);
+    }
+
+    #[test]
+    fn constraint_parses_operator_and_version() {
+        let c = Constraint::parse("glibc>=2.28");
+        assert_eq!(c.name, "glibc");
+        assert_eq!(c.op, Some(Op::GreaterEq));
+        assert!(c.matches(&Version::parse("2.28")));
+        assert!(c.matches(&Version::parse("2.30")));
+        assert!(!c.matches(&Version::parse("2.20")));
+    }
+
+    #[test]
+    fn constraint_with_epoch() {
+        let c = Constraint::parse("bar<3:1.0");
+        assert_eq!(c.name, "bar");
+        assert_eq!(c.op, Some(Op::Less));
+        assert!(c.matches(&Version::parse("2:9.0")));
+        assert!(!c.matches(&Version::parse("3:1.0")));
+    }
+
+    #[test]
+    fn bare_name_matches_any_version() {
+        let c = Constraint::parse("foo");
+        assert!(c.op.is_none());
+        assert!(c.matches(&Version::parse("1.0-1")));
+    }
+}